@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use crate::{MatchMetadata, MetaValue, MetadataProvider};
+
+/// A metadata value bucketed for categorical faceting.
+///
+/// `Str`, `Int`, and `Bool` values are faceted categorically (distinct
+/// value → count). `Float` has no categorical bucket, since floating
+/// point equality makes poor facet keys; it is only ever folded into a
+/// key's [`NumericStats`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FacetValue {
+    Str(String),
+    Int(i64),
+    Bool(bool),
+}
+
+fn categorical_value(value: &MetaValue) -> Option<FacetValue> {
+    match value {
+        MetaValue::Str(s) => Some(FacetValue::Str(s.to_string())),
+        MetaValue::Int(i) => Some(FacetValue::Int(*i)),
+        MetaValue::Bool(b) => Some(FacetValue::Bool(*b)),
+        MetaValue::Float(_) => None,
+    }
+}
+
+/// Returns `value` promoted to `f64` if it is numeric (`Int` or
+/// `Float`), promoting `Int` by conversion.
+fn numeric_value(value: &MetaValue) -> Option<f64> {
+    match *value {
+        MetaValue::Int(i) => Some(i as f64),
+        MetaValue::Float(f) => Some(f),
+        _ => None,
+    }
+}
+
+/// Running `min`/`max`/`sum`/`count` statistics over a metadata key's
+/// numeric (`Int`/`Float`) values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumericStats {
+    pub min: f64,
+    pub max: f64,
+    pub sum: f64,
+    pub count: u64,
+}
+
+impl NumericStats {
+    fn fold(previous: Option<NumericStats>, value: f64) -> NumericStats {
+        match previous {
+            Some(stats) => NumericStats {
+                min: stats.min.min(value),
+                max: stats.max.max(value),
+                sum: stats.sum + value,
+                count: stats.count + 1,
+            },
+            None => NumericStats { min: value, max: value, sum: value, count: 1 },
+        }
+    }
+
+    /// Returns the arithmetic mean, or `None` if `count` is zero.
+    pub fn mean(&self) -> Option<f64> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(self.sum / self.count as f64)
+        }
+    }
+}
+
+/// The facet summary for a single metadata key: categorical value
+/// counts, numeric stats (if any values were numeric), and a count of
+/// matches whose metadata had no value for this key at all.
+#[derive(Debug, Clone, Default)]
+pub struct FacetSummary {
+    pub counts: HashMap<FacetValue, u64>,
+    pub numeric: Option<NumericStats>,
+    pub missing: u64,
+}
+
+impl FacetSummary {
+    fn add(&mut self, value: &MetaValue) {
+        if let Some(facet_value) = categorical_value(value) {
+            *self.counts.entry(facet_value).or_insert(0) += 1;
+        }
+        if let Some(n) = numeric_value(value) {
+            self.numeric = Some(NumericStats::fold(self.numeric, n));
+        }
+    }
+}
+
+/// Per-key facet summaries produced by a [`FacetAccumulator`].
+#[derive(Debug, Clone, Default)]
+pub struct FacetResults {
+    summaries: HashMap<String, FacetSummary>,
+}
+
+impl FacetResults {
+    /// Returns the facet summary for `key`, if it was requested.
+    pub fn get(&self, key: &str) -> Option<&FacetSummary> {
+        self.summaries.get(key)
+    }
+}
+
+/// Summarizes metadata across a set of search matches, answering
+/// reporting questions like "how many matches fell on each chapter" or
+/// "what page range did hits span" after a search has completed.
+///
+/// This is a reporting layer on top of [`MetadataProvider`]: where a
+/// provider answers "what metadata applies at this offset", a
+/// `FacetAccumulator` answers "what does the metadata look like across
+/// every offset a search touched".
+pub struct FacetAccumulator {
+    keys: Vec<String>,
+    summaries: HashMap<String, FacetSummary>,
+}
+
+impl FacetAccumulator {
+    /// Creates an accumulator that tracks facets for the given metadata
+    /// keys.
+    pub fn new(keys: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let keys: Vec<String> = keys.into_iter().map(Into::into).collect();
+        let summaries = keys.iter().cloned().map(|key| (key, FacetSummary::default())).collect();
+        Self { keys, summaries }
+    }
+
+    /// Folds a single match's metadata into the running facets.
+    ///
+    /// A requested key with no value in `meta` increments that key's
+    /// `missing` count.
+    pub fn add(&mut self, meta: &MatchMetadata) {
+        for key in &self.keys {
+            let summary = self.summaries.get_mut(key).expect("key was registered in new()");
+            match meta.get(key) {
+                Some(value) => summary.add(value),
+                None => summary.missing += 1,
+            }
+        }
+    }
+
+    /// Folds the metadata for every offset in `offsets`, as resolved by
+    /// `provider`, into the running facets.
+    ///
+    /// An offset with no metadata at all counts as missing for every
+    /// requested key.
+    pub fn add_offsets(
+        &mut self,
+        offsets: impl IntoIterator<Item = u64>,
+        provider: &dyn MetadataProvider,
+    ) {
+        for offset in offsets {
+            match provider.metadata_for_offset(offset) {
+                Some(meta) => self.add(meta),
+                None => {
+                    for summary in self.summaries.values_mut() {
+                        summary.missing += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Consumes the accumulator, returning the final facet results.
+    pub fn finish(self) -> FacetResults {
+        FacetResults { summaries: self.summaries }
+    }
+}
@@ -0,0 +1,76 @@
+use std::borrow::Cow;
+
+use crate::{MatchMetadata, MetaValue};
+
+/// A comparison operator used by a [`Where::Comparison`] leaf.
+///
+/// Ordering operators (`Gt`, `Gte`, `Lt`, `Lte`) use [`MetaValue::compare`],
+/// which compares `Int`/`Float` numerically (promoting `Int` to `f64`),
+/// `Str` lexicographically, and `Bool` with `false < true`; comparing
+/// values of incompatible types is a type mismatch and evaluates to
+/// `false`. `Contains` is a substring test and only matches when both
+/// the stored value and the comparison value are `Str`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    In(Vec<MetaValue>),
+    NotIn(Vec<MetaValue>),
+    Contains,
+}
+
+/// A predicate tree for restricting matches by their [`MatchMetadata`].
+///
+/// Leaves compare a single metadata key against a [`MetaValue`], or
+/// simply check that a key is present (`Exists`); the `And`/`Or`/`Not`
+/// combinators build up boolean expressions over them. A missing key,
+/// or a type mismatch in an ordering or `Contains` comparison (e.g.
+/// `Gt` applied to a `Str`), makes a `Comparison` leaf evaluate to
+/// `false` rather than erroring. `Ne` and `NotIn` use plain equality,
+/// so a stored value of a different type than the comparison's
+/// evaluates to `true`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Where {
+    Comparison { key: Cow<'static, str>, op: CompareOp, value: MetaValue },
+    Exists(Cow<'static, str>),
+    And(Vec<Where>),
+    Or(Vec<Where>),
+    Not(Box<Where>),
+}
+
+impl Where {
+    /// Evaluates this predicate against the given metadata.
+    pub fn eval(&self, metadata: &MatchMetadata) -> bool {
+        match self {
+            Where::Comparison { key, op, value } => match metadata.get(key) {
+                Some(actual) => eval_comparison(actual, op, value),
+                None => false,
+            },
+            Where::Exists(key) => metadata.get(key).is_some(),
+            Where::And(children) => children.iter().all(|w| w.eval(metadata)),
+            Where::Or(children) => children.iter().any(|w| w.eval(metadata)),
+            Where::Not(inner) => !inner.eval(metadata),
+        }
+    }
+}
+
+fn eval_comparison(actual: &MetaValue, op: &CompareOp, value: &MetaValue) -> bool {
+    match op {
+        CompareOp::Eq => actual == value,
+        CompareOp::Ne => actual != value,
+        CompareOp::Gt => actual.compare(value).map_or(false, |o| o.is_gt()),
+        CompareOp::Gte => actual.compare(value).map_or(false, |o| o.is_ge()),
+        CompareOp::Lt => actual.compare(value).map_or(false, |o| o.is_lt()),
+        CompareOp::Lte => actual.compare(value).map_or(false, |o| o.is_le()),
+        CompareOp::In(values) => values.contains(actual),
+        CompareOp::NotIn(values) => !values.contains(actual),
+        CompareOp::Contains => match (actual, value) {
+            (MetaValue::Str(actual), MetaValue::Str(needle)) => actual.contains(needle.as_ref()),
+            _ => false,
+        },
+    }
+}
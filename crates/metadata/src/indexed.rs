@@ -0,0 +1,306 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Bound;
+
+use roaring::RoaringBitmap;
+
+use crate::{CompareOp, MatchMetadata, MetaRegion, MetaValue, MetadataProvider, Where};
+
+/// A total-ordering key derived from a [`MetaValue`], suitable for use as
+/// a `BTreeMap` key (`f64` alone is not `Ord`).
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum MetaValueKey {
+    Str(String),
+    Int(i64),
+    Float(OrderedF64),
+    Bool(bool),
+}
+
+impl MetaValueKey {
+    fn from_value(value: &MetaValue) -> Self {
+        match value {
+            MetaValue::Str(s) => MetaValueKey::Str(s.to_string()),
+            MetaValue::Int(i) => MetaValueKey::Int(*i),
+            MetaValue::Float(f) => MetaValueKey::Float(OrderedF64(*f)),
+            MetaValue::Bool(b) => MetaValueKey::Bool(*b),
+        }
+    }
+}
+
+/// An `f64` newtype with a total order (via [`f64::total_cmp`]), so it can
+/// key the numeric range index.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct OrderedF64(f64);
+
+impl Eq for OrderedF64 {}
+
+impl PartialOrd for OrderedF64 {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrderedF64 {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// Returns `value` promoted to an [`OrderedF64`] if it is numeric
+/// (`Int` or `Float`), promoting `Int` to `f64`.
+fn numeric_key(value: &MetaValue) -> Option<OrderedF64> {
+    match *value {
+        MetaValue::Int(i) => Some(OrderedF64(i as f64)),
+        MetaValue::Float(f) => Some(OrderedF64(f)),
+        _ => None,
+    }
+}
+
+/// Inverted indexes built over a single metadata key.
+#[derive(Debug, Default)]
+struct KeyIndex {
+    /// Exact-value lookup: every `MetaValue` seen under this key maps to
+    /// the bitmap of regions carrying it.
+    by_value: BTreeMap<MetaValueKey, RoaringBitmap>,
+    /// Numeric-only index (`Int` promoted to `f64`) so ordering queries
+    /// can resolve via `BTreeMap::range` instead of scanning `by_value`.
+    by_numeric: BTreeMap<OrderedF64, RoaringBitmap>,
+}
+
+/// A [`MetadataProvider`] that answers [`Where`] queries in sub-linear
+/// time over collections with thousands of regions (e.g. per-page
+/// metadata in a large PDF), by maintaining an inverted index per
+/// metadata key.
+///
+/// For each key, `Eq`/`In`/`Exists` resolve via direct bitmap lookups and
+/// `Gt`/`Gte`/`Lt`/`Lte` resolve via a `BTreeMap::range` over a
+/// numeric-only index, unioning the bitmaps in range. `And`/`Or` combine
+/// child bitmaps with intersection/union; an empty `And` resolves to
+/// every region (vacuously true, agreeing with `Where::eval`'s
+/// `Iterator::all` and
+/// [`VecMetaProvider::filter_regions`](crate::VecMetaProvider::filter_regions)'s
+/// same rule), and an empty `Or` resolves to none. Predicate shapes the index can't
+/// accelerate (`Contains`, `NotIn`, and ordering comparisons against a
+/// non-numeric value) fall back to a linear scan of `regions`.
+///
+/// Point lookup by offset still works (via [`MetadataProvider`]), using
+/// the same linear "last region wins" semantics as
+/// [`VecMetaProvider`](crate::VecMetaProvider), which remains the
+/// unindexed, lower-overhead choice for collections too small to
+/// benefit from indexing.
+#[derive(Debug)]
+pub struct IndexedMetaProvider {
+    regions: Vec<MetaRegion>,
+    indexes: HashMap<Cow<'static, str>, KeyIndex>,
+}
+
+impl IndexedMetaProvider {
+    /// Creates a new provider from a vector of regions, building the
+    /// inverted indexes over their metadata.
+    ///
+    /// Empty regions (where `start >= end`) are silently ignored.
+    pub fn new(mut regions: Vec<MetaRegion>) -> Self {
+        regions.retain(|r| !r.is_empty());
+        regions.sort_by_key(|r| r.start);
+
+        let mut indexes: HashMap<Cow<'static, str>, KeyIndex> = HashMap::new();
+        for (i, region) in regions.iter().enumerate() {
+            for (key, value) in region.meta.iter() {
+                let index = indexes.entry(key.clone()).or_default();
+                index
+                    .by_value
+                    .entry(MetaValueKey::from_value(value))
+                    .or_default()
+                    .insert(i as u32);
+                if let Some(numeric) = numeric_key(value) {
+                    index.by_numeric.entry(numeric).or_default().insert(i as u32);
+                }
+            }
+        }
+
+        Self { regions, indexes }
+    }
+
+    /// Returns the number of regions in this provider.
+    pub fn region_count(&self) -> usize {
+        self.regions.len()
+    }
+
+    /// Returns a slice of all regions (sorted by start offset).
+    pub fn regions(&self) -> &[MetaRegion] {
+        &self.regions
+    }
+
+    /// Returns every region whose metadata satisfies `filter`, resolved
+    /// through the inverted indexes where possible.
+    pub fn select_regions(&self, filter: &Where) -> Vec<&MetaRegion> {
+        self.resolve(filter)
+            .into_iter()
+            .map(|i| &self.regions[i as usize])
+            .collect()
+    }
+
+    fn resolve(&self, filter: &Where) -> RoaringBitmap {
+        match filter {
+            Where::Comparison { key, op, value } => self.resolve_comparison(key, op, value, filter),
+            Where::Exists(key) => self.has_key_bitmap(key),
+            Where::And(children) => children
+                .iter()
+                .map(|child| self.resolve(child))
+                .reduce(|mut acc, next| {
+                    acc &= next;
+                    acc
+                })
+                .unwrap_or_else(|| self.all_bitmap()),
+            Where::Or(children) => children.iter().fold(RoaringBitmap::new(), |mut acc, child| {
+                acc |= self.resolve(child);
+                acc
+            }),
+            Where::Not(inner) => {
+                let mut all = self.all_bitmap();
+                all -= self.resolve(inner);
+                all
+            }
+        }
+    }
+
+    /// Resolves a single `Where::Comparison` leaf. `filter` is the same
+    /// leaf, passed through for the `scan` fallback.
+    fn resolve_comparison(
+        &self,
+        key: &str,
+        op: &CompareOp,
+        value: &MetaValue,
+        filter: &Where,
+    ) -> RoaringBitmap {
+        match op {
+            CompareOp::Eq => self.eq_bitmap(key, value),
+            CompareOp::Ne => {
+                let mut bitmap = self.has_key_bitmap(key);
+                bitmap -= self.eq_bitmap(key, value);
+                bitmap
+            }
+            CompareOp::In(values) => {
+                let mut bitmap = RoaringBitmap::new();
+                for value in values {
+                    bitmap |= self.eq_bitmap(key, value);
+                }
+                bitmap
+            }
+            CompareOp::Gt => self.ordering_bitmap(
+                key,
+                value,
+                |t| (Bound::Excluded(t), Bound::Unbounded),
+                |o| o.is_gt(),
+            ),
+            CompareOp::Gte => self.ordering_bitmap(
+                key,
+                value,
+                |t| (Bound::Included(t), Bound::Unbounded),
+                |o| o.is_ge(),
+            ),
+            CompareOp::Lt => self.ordering_bitmap(
+                key,
+                value,
+                |t| (Bound::Unbounded, Bound::Excluded(t)),
+                |o| o.is_lt(),
+            ),
+            CompareOp::Lte => self.ordering_bitmap(
+                key,
+                value,
+                |t| (Bound::Unbounded, Bound::Included(t)),
+                |o| o.is_le(),
+            ),
+            // Not accelerated by the index: fall back to a linear scan.
+            CompareOp::NotIn(_) | CompareOp::Contains => self.scan(filter),
+        }
+    }
+
+    fn eq_bitmap(&self, key: &str, value: &MetaValue) -> RoaringBitmap {
+        self.indexes
+            .get(key)
+            .and_then(|index| index.by_value.get(&MetaValueKey::from_value(value)))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn has_key_bitmap(&self, key: &str) -> RoaringBitmap {
+        self.indexes
+            .get(key)
+            .map(|index| {
+                index.by_value.values().fold(RoaringBitmap::new(), |mut acc, bitmap| {
+                    acc |= bitmap;
+                    acc
+                })
+            })
+            .unwrap_or_default()
+    }
+
+    fn all_bitmap(&self) -> RoaringBitmap {
+        (0..self.regions.len() as u32).collect()
+    }
+
+    /// Resolves an ordering comparison (`Gt`/`Gte`/`Lt`/`Lte`) against
+    /// `key`. When `value` is numeric, this ranges over the `by_numeric`
+    /// index; otherwise it falls back to a linear scan using
+    /// [`MetaValue::compare`].
+    fn ordering_bitmap(
+        &self,
+        key: &str,
+        value: &MetaValue,
+        bound: impl FnOnce(OrderedF64) -> (Bound<OrderedF64>, Bound<OrderedF64>),
+        accept: impl Fn(std::cmp::Ordering) -> bool,
+    ) -> RoaringBitmap {
+        match (self.indexes.get(key), numeric_key(value)) {
+            (Some(index), Some(target)) => {
+                let (lo, hi) = bound(target);
+                index
+                    .by_numeric
+                    .range((lo, hi))
+                    .fold(RoaringBitmap::new(), |mut acc, (_, bitmap)| {
+                        acc |= bitmap;
+                        acc
+                    })
+            }
+            _ => self.scan_ordering(key, value, accept),
+        }
+    }
+
+    fn scan_ordering(
+        &self,
+        key: &str,
+        value: &MetaValue,
+        accept: impl Fn(std::cmp::Ordering) -> bool,
+    ) -> RoaringBitmap {
+        self.regions
+            .iter()
+            .enumerate()
+            .filter(|(_, region)| {
+                region
+                    .meta
+                    .get(key)
+                    .and_then(|actual| actual.compare(value))
+                    .map_or(false, &accept)
+            })
+            .map(|(i, _)| i as u32)
+            .collect()
+    }
+
+    fn scan(&self, filter: &Where) -> RoaringBitmap {
+        self.regions
+            .iter()
+            .enumerate()
+            .filter(|(_, region)| filter.eval(&region.meta))
+            .map(|(i, _)| i as u32)
+            .collect()
+    }
+}
+
+impl MetadataProvider for IndexedMetaProvider {
+    fn metadata_for_offset(&self, offset: u64) -> Option<&MatchMetadata> {
+        self.regions
+            .iter()
+            .rfind(|r| r.contains(offset))
+            .map(|r| &r.meta)
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{MatchMetadata, MetaRegion, MetadataProvider};
+
+/// A node of the centered interval tree built by [`IntervalTreeMetaProvider`].
+///
+/// `by_start` and `by_end` both index into the provider's `regions` vector
+/// and hold only the regions that straddle this node's `center`: the
+/// former sorted by start offset ascending, the latter by end offset
+/// descending, so a query can scan from the front and stop as soon as the
+/// endpoints no longer bracket the queried offset.
+#[derive(Debug, Clone)]
+struct Node {
+    center: u64,
+    by_start: Vec<usize>,
+    by_end: Vec<usize>,
+    left: Option<Box<Node>>,
+    right: Option<Box<Node>>,
+}
+
+/// A [`MetadataProvider`] backed by a centered interval tree.
+///
+/// Unlike [`VecMetaProvider`](crate::VecMetaProvider), which does a linear
+/// `rfind` scan, this answers `metadata_for_offset` in `O(log n + k)` time
+/// where `k` is the number of regions overlapping the query point. This
+/// matters once a document has thousands of regions, e.g. per-page
+/// metadata in a large PDF or per-cue metadata in a long subtitle track.
+///
+/// # Overlapping regions
+///
+/// As with `VecMetaProvider`, if multiple regions contain an offset, the
+/// one inserted last (highest original index) wins.
+#[derive(Debug, Clone)]
+pub struct IntervalTreeMetaProvider {
+    regions: Vec<MetaRegion>,
+    root: Option<Box<Node>>,
+}
+
+impl IntervalTreeMetaProvider {
+    /// Creates a new provider from a vector of regions.
+    ///
+    /// Empty regions (where `start >= end`) are silently ignored. Regions
+    /// are kept in their original (insertion) order internally so that
+    /// overlap resolution can prefer the most recently inserted region.
+    pub fn new(mut regions: Vec<MetaRegion>) -> Self {
+        regions.retain(|r| !r.is_empty());
+        let indices: Vec<usize> = (0..regions.len()).collect();
+        let root = build_node(&regions, indices);
+        IntervalTreeMetaProvider { regions, root }
+    }
+
+    /// Returns the number of regions in this provider.
+    pub fn region_count(&self) -> usize {
+        self.regions.len()
+    }
+
+    /// Returns the underlying regions, in their original insertion order.
+    pub fn regions(&self) -> &[MetaRegion] {
+        &self.regions
+    }
+
+    /// Descends the tree collecting the index of every region that
+    /// contains `offset`, in the order encountered (not insertion order).
+    fn hits(&self, offset: u64) -> Vec<usize> {
+        let mut node = self.root.as_deref();
+        let mut hits = Vec::new();
+        while let Some(n) = node {
+            if offset <= n.center {
+                for &i in &n.by_start {
+                    if self.regions[i].start > offset {
+                        break;
+                    }
+                    if self.regions[i].contains(offset) {
+                        hits.push(i);
+                    }
+                }
+            } else {
+                for &i in &n.by_end {
+                    if self.regions[i].end <= offset {
+                        break;
+                    }
+                    if self.regions[i].contains(offset) {
+                        hits.push(i);
+                    }
+                }
+            }
+            node = if offset < n.center {
+                n.left.as_deref()
+            } else if offset > n.center {
+                n.right.as_deref()
+            } else {
+                None
+            };
+        }
+        hits
+    }
+
+    /// Returns every region containing `offset`, in ascending insertion
+    /// order, for callers that want the raw overlap stack rather than a
+    /// single merged result.
+    pub fn regions_for_offset(&self, offset: u64) -> Vec<&MetaRegion> {
+        let mut hits = self.hits(offset);
+        hits.sort_unstable();
+        hits.into_iter().map(|i| &self.regions[i]).collect()
+    }
+
+    /// Returns the metadata of every region containing `offset`, merged
+    /// into a single [`MatchMetadata`].
+    ///
+    /// Unlike [`metadata_for_offset`](MetadataProvider::metadata_for_offset),
+    /// which only returns the most recently inserted region's metadata,
+    /// this preserves outer-scope keys that an inner region doesn't
+    /// override: hits are folded widest region first (by descending
+    /// length), so a narrower, more specific region's keys win over a
+    /// wider, more general region's keys of the same name.
+    pub fn metadata_for_offset_merged(&self, offset: u64) -> Option<MatchMetadata> {
+        let mut hits = self.hits(offset);
+        if hits.is_empty() {
+            return None;
+        }
+        hits.sort_by_key(|&i| std::cmp::Reverse(self.regions[i].len()));
+
+        let mut merged = MatchMetadata::new();
+        for i in hits {
+            for (key, value) in self.regions[i].meta.iter() {
+                merged.insert(key.clone(), value.clone());
+            }
+        }
+        Some(merged)
+    }
+}
+
+fn build_node(regions: &[MetaRegion], indices: Vec<usize>) -> Option<Box<Node>> {
+    if indices.is_empty() {
+        return None;
+    }
+
+    let mut endpoints: Vec<u64> = Vec::with_capacity(indices.len() * 2);
+    for &i in &indices {
+        endpoints.push(regions[i].start);
+        endpoints.push(regions[i].end);
+    }
+    endpoints.sort_unstable();
+    // The lower median: with the upper one, a set whose ends all sit at
+    // the center (e.g. a single region) would go left unchanged forever.
+    let center = endpoints[(endpoints.len() - 1) / 2];
+
+    let mut here = Vec::new();
+    let mut left = Vec::new();
+    let mut right = Vec::new();
+    for i in indices {
+        let r = &regions[i];
+        if r.end <= center {
+            left.push(i);
+        } else if r.start > center {
+            right.push(i);
+        } else {
+            here.push(i);
+        }
+    }
+
+    let mut by_start = here.clone();
+    by_start.sort_by_key(|&i| regions[i].start);
+    let mut by_end = here;
+    by_end.sort_by_key(|&i| std::cmp::Reverse(regions[i].end));
+
+    Some(Box::new(Node {
+        center,
+        by_start,
+        by_end,
+        left: build_node(regions, left),
+        right: build_node(regions, right),
+    }))
+}
+
+impl MetadataProvider for IntervalTreeMetaProvider {
+    fn metadata_for_offset(&self, offset: u64) -> Option<&MatchMetadata> {
+        self.hits(offset).into_iter().max().map(|i| &self.regions[i].meta)
+    }
+}
@@ -41,11 +41,21 @@ mod value;
 mod metadata;
 mod region;
 mod provider;
+mod filter;
+mod interval;
+mod indexed;
+mod facet;
+mod sort;
 
 pub use value::MetaValue;
 pub use metadata::MatchMetadata;
 pub use region::MetaRegion;
 pub use provider::{MetadataProvider, VecMetaProvider};
+pub use filter::{CompareOp, Where};
+pub use interval::IntervalTreeMetaProvider;
+pub use indexed::IndexedMetaProvider;
+pub use facet::{FacetAccumulator, FacetResults, FacetSummary, FacetValue, NumericStats};
+pub use sort::{MetaSort, SortOrder};
 
 #[cfg(test)]
 mod tests {
@@ -353,4 +363,609 @@ mod tests {
 
         assert_eq!(meta2.get("page"), Some(&MetaValue::Int(42)));
     }
+
+    // ========================================================================
+    // Step 3.1 Tests: Where predicate filtering
+    // ========================================================================
+
+    #[test]
+    fn test_where_comparison_operators() {
+        let mut meta = MatchMetadata::new();
+        meta.insert("page", MetaValue::Int(7));
+
+        let gt5 = Where::Comparison {
+            key: "page".into(),
+            op: CompareOp::Gt,
+            value: MetaValue::Int(5),
+        };
+        assert!(gt5.eval(&meta));
+
+        let lt5 = Where::Comparison {
+            key: "page".into(),
+            op: CompareOp::Lt,
+            value: MetaValue::Int(5),
+        };
+        assert!(!lt5.eval(&meta));
+
+        // Int/Float are compared uniformly by promoting to f64.
+        let gt_float = Where::Comparison {
+            key: "page".into(),
+            op: CompareOp::Gte,
+            value: MetaValue::Float(7.0),
+        };
+        assert!(gt_float.eval(&meta));
+
+        meta.insert("chapter", MetaValue::Str("Appendix".into()));
+        let in_set = Where::Comparison {
+            key: "chapter".into(),
+            op: CompareOp::In(vec!["Intro".into(), "Appendix".into()]),
+            value: MetaValue::Bool(true),
+        };
+        assert!(in_set.eval(&meta));
+
+        let not_in = Where::Comparison {
+            key: "chapter".into(),
+            op: CompareOp::NotIn(vec!["Intro".into()]),
+            value: MetaValue::Bool(true),
+        };
+        assert!(not_in.eval(&meta));
+    }
+
+    #[test]
+    fn test_where_missing_key_and_type_mismatch_are_false() {
+        let meta = MatchMetadata::new();
+        let missing = Where::Comparison {
+            key: "page".into(),
+            op: CompareOp::Eq,
+            value: MetaValue::Int(1),
+        };
+        assert!(!missing.eval(&meta));
+
+        let mut meta = MatchMetadata::new();
+        meta.insert("chapter", MetaValue::Str("Intro".into()));
+        let mismatch = Where::Comparison {
+            key: "chapter".into(),
+            op: CompareOp::Gt,
+            value: MetaValue::Int(1),
+        };
+        assert!(!mismatch.eval(&meta));
+    }
+
+    #[test]
+    fn test_where_and_or_not() {
+        let mut meta = MatchMetadata::new();
+        meta.insert("page", MetaValue::Int(7));
+
+        let gt5 = Where::Comparison {
+            key: "page".into(),
+            op: CompareOp::Gt,
+            value: MetaValue::Int(5),
+        };
+        let lt10 = Where::Comparison {
+            key: "page".into(),
+            op: CompareOp::Lt,
+            value: MetaValue::Int(10),
+        };
+        assert!(Where::And(vec![gt5.clone(), lt10.clone()]).eval(&meta));
+
+        let gt100 = Where::Comparison {
+            key: "page".into(),
+            op: CompareOp::Gt,
+            value: MetaValue::Int(100),
+        };
+        assert!(Where::Or(vec![gt100.clone(), lt10]).eval(&meta));
+        assert!(Where::Not(Box::new(gt100)).eval(&meta));
+    }
+
+    #[test]
+    fn test_vec_provider_filter_regions() {
+        let mut meta1 = MatchMetadata::new();
+        meta1.insert("page", MetaValue::Int(1));
+        let mut meta2 = MatchMetadata::new();
+        meta2.insert("page", MetaValue::Int(5));
+        let mut meta3 = MatchMetadata::new();
+        meta3.insert("page", MetaValue::Int(9));
+
+        let provider = VecMetaProvider::new(vec![
+            MetaRegion { start: 0, end: 10, meta: meta1 },
+            MetaRegion { start: 10, end: 20, meta: meta2 },
+            MetaRegion { start: 20, end: 30, meta: meta3 },
+        ]);
+
+        let gt3 = Where::Comparison {
+            key: "page".into(),
+            op: CompareOp::Gt,
+            value: MetaValue::Int(3),
+        };
+        assert_eq!(provider.filter_regions(&gt3), vec![1, 2]);
+
+        let lt3 = Where::Comparison {
+            key: "page".into(),
+            op: CompareOp::Lt,
+            value: MetaValue::Int(3),
+        };
+        assert_eq!(
+            provider.filter_regions(&Where::And(vec![gt3.clone(), lt3.clone()])),
+            Vec::<usize>::new()
+        );
+        assert_eq!(provider.filter_regions(&Where::Or(vec![gt3, lt3])), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_vec_provider_filter_regions_empty_combinators_agree_with_eval() {
+        let mut meta = MatchMetadata::new();
+        meta.insert("page", MetaValue::Int(1));
+        let provider = VecMetaProvider::new(vec![MetaRegion { start: 0, end: 10, meta: meta.clone() }]);
+
+        // `And(vec![])` is vacuously true (`Iterator::all` on empty),
+        // so it must match every region, just like `Where::eval` does.
+        assert!(Where::And(vec![]).eval(&meta));
+        assert_eq!(provider.filter_regions(&Where::And(vec![])), vec![0]);
+
+        // `Or(vec![])` is vacuously false (`Iterator::any` on empty).
+        assert!(!Where::Or(vec![]).eval(&meta));
+        assert_eq!(provider.filter_regions(&Where::Or(vec![])), Vec::<usize>::new());
+    }
+
+    // ========================================================================
+    // Step 3.2 Tests: IntervalTreeMetaProvider
+    // ========================================================================
+
+    #[test]
+    fn test_interval_tree_basic_lookup() {
+        let mut meta1 = MatchMetadata::new();
+        meta1.insert("page", MetaValue::Int(1));
+        let mut meta2 = MatchMetadata::new();
+        meta2.insert("page", MetaValue::Int(2));
+        let mut meta3 = MatchMetadata::new();
+        meta3.insert("page", MetaValue::Int(3));
+
+        let provider = IntervalTreeMetaProvider::new(vec![
+            MetaRegion { start: 0, end: 100, meta: meta1 },
+            MetaRegion { start: 100, end: 200, meta: meta2 },
+            MetaRegion { start: 200, end: 300, meta: meta3 },
+        ]);
+
+        assert_eq!(
+            provider.metadata_for_offset(50).unwrap().get("page"),
+            Some(&MetaValue::Int(1))
+        );
+        assert_eq!(
+            provider.metadata_for_offset(150).unwrap().get("page"),
+            Some(&MetaValue::Int(2))
+        );
+        assert_eq!(
+            provider.metadata_for_offset(250).unwrap().get("page"),
+            Some(&MetaValue::Int(3))
+        );
+        assert!(provider.metadata_for_offset(400).is_none());
+        // Start is inclusive.
+        assert_eq!(
+            provider.metadata_for_offset(100).unwrap().get("page"),
+            Some(&MetaValue::Int(2))
+        );
+    }
+
+    #[test]
+    fn test_interval_tree_overlap_last_wins() {
+        let mut outer = MatchMetadata::new();
+        outer.insert("tag", MetaValue::Str("outer".into()));
+        let mut inner = MatchMetadata::new();
+        inner.insert("tag", MetaValue::Str("inner".into()));
+
+        let provider = IntervalTreeMetaProvider::new(vec![
+            MetaRegion { start: 0, end: 100, meta: outer },
+            MetaRegion { start: 50, end: 75, meta: inner },
+        ]);
+
+        assert_eq!(
+            provider.metadata_for_offset(60).unwrap().get("tag"),
+            Some(&MetaValue::Str("inner".into()))
+        );
+        assert_eq!(
+            provider.metadata_for_offset(30).unwrap().get("tag"),
+            Some(&MetaValue::Str("outer".into()))
+        );
+        assert_eq!(
+            provider.metadata_for_offset(80).unwrap().get("tag"),
+            Some(&MetaValue::Str("outer".into()))
+        );
+    }
+
+    #[test]
+    fn test_interval_tree_empty() {
+        let provider = IntervalTreeMetaProvider::new(vec![]);
+        assert!(provider.metadata_for_offset(0).is_none());
+        assert_eq!(provider.region_count(), 0);
+    }
+
+    #[test]
+    fn test_interval_tree_many_regions() {
+        // Exercise the tree's recursive splitting with enough regions that
+        // it can't all fit in one node.
+        let mut regions = Vec::new();
+        for i in 0..2000u64 {
+            let mut meta = MatchMetadata::new();
+            meta.insert("page", MetaValue::Int(i as i64));
+            regions.push(MetaRegion::new(i * 10, i * 10 + 10, meta));
+        }
+        let provider = IntervalTreeMetaProvider::new(regions);
+
+        for i in 0..2000u64 {
+            let meta = provider.metadata_for_offset(i * 10 + 5).unwrap();
+            assert_eq!(meta.get("page"), Some(&MetaValue::Int(i as i64)));
+        }
+        assert!(provider.metadata_for_offset(20_000).is_none());
+    }
+
+    #[test]
+    fn test_interval_tree_merged_metadata_prefers_narrower_region() {
+        let mut outer = MatchMetadata::new();
+        outer.insert("page", MetaValue::Int(1));
+        outer.insert("section", MetaValue::Str("outer".into()));
+        let mut inner = MatchMetadata::new();
+        inner.insert("section", MetaValue::Str("inner".into()));
+
+        let provider = IntervalTreeMetaProvider::new(vec![
+            MetaRegion { start: 0, end: 100, meta: outer },
+            MetaRegion { start: 40, end: 60, meta: inner },
+        ]);
+
+        // Inside the inner region: "page" is inherited from the outer
+        // region, but "section" is overridden by the narrower one.
+        let merged = provider.metadata_for_offset_merged(50).unwrap();
+        assert_eq!(merged.get("page"), Some(&MetaValue::Int(1)));
+        assert_eq!(merged.get("section"), Some(&MetaValue::Str("inner".into())));
+
+        // Outside the inner region, only the outer region's metadata applies.
+        let merged = provider.metadata_for_offset_merged(10).unwrap();
+        assert_eq!(merged.get("section"), Some(&MetaValue::Str("outer".into())));
+
+        assert!(provider.metadata_for_offset_merged(200).is_none());
+    }
+
+    #[test]
+    fn test_interval_tree_regions_for_offset() {
+        let mut outer = MatchMetadata::new();
+        outer.insert("tag", MetaValue::Str("outer".into()));
+        let mut inner = MatchMetadata::new();
+        inner.insert("tag", MetaValue::Str("inner".into()));
+
+        let provider = IntervalTreeMetaProvider::new(vec![
+            MetaRegion { start: 0, end: 100, meta: outer },
+            MetaRegion { start: 40, end: 60, meta: inner },
+        ]);
+
+        let stack = provider.regions_for_offset(50);
+        assert_eq!(stack.len(), 2);
+        assert_eq!(stack[0].meta.get("tag"), Some(&MetaValue::Str("outer".into())));
+        assert_eq!(stack[1].meta.get("tag"), Some(&MetaValue::Str("inner".into())));
+
+        assert!(provider.regions_for_offset(200).is_empty());
+    }
+
+    // ========================================================================
+    // Step 3.3 Tests: Where::Exists and Where::Comparison { op: Contains }
+    // ========================================================================
+
+    fn comparison(key: &'static str, op: CompareOp, value: MetaValue) -> Where {
+        Where::Comparison { key: key.into(), op, value }
+    }
+
+    #[test]
+    fn test_where_contains_and_exists() {
+        let mut meta = MatchMetadata::new();
+        meta.insert("page", MetaValue::Int(7));
+        meta.insert("chapter", MetaValue::Str("Appendix".into()));
+
+        let contains = comparison("chapter", CompareOp::Contains, MetaValue::Str("pend".into()));
+        assert!(contains.eval(&meta));
+        let no_match = comparison("chapter", CompareOp::Contains, MetaValue::Str("Intro".into()));
+        assert!(!no_match.eval(&meta));
+        // `Contains` only matches `Str` values.
+        let wrong_type = comparison("page", CompareOp::Contains, MetaValue::Str("7".into()));
+        assert!(!wrong_type.eval(&meta));
+
+        assert!(Where::Exists("page".into()).eval(&meta));
+        assert!(!Where::Exists("missing".into()).eval(&meta));
+    }
+
+    #[test]
+    fn test_where_not_exists_on_missing_key() {
+        let meta = MatchMetadata::new();
+
+        assert!(!Where::Exists("page".into()).eval(&meta));
+        assert!(Where::Not(Box::new(Where::Exists("page".into()))).eval(&meta));
+    }
+
+    #[test]
+    fn test_vec_provider_filter_offset() {
+        let mut meta1 = MatchMetadata::new();
+        meta1.insert("page", MetaValue::Int(1));
+        let mut meta2 = MatchMetadata::new();
+        meta2.insert("page", MetaValue::Int(9));
+
+        let provider = VecMetaProvider::new(vec![
+            MetaRegion { start: 0, end: 10, meta: meta1 },
+            MetaRegion { start: 10, end: 20, meta: meta2 },
+        ]);
+
+        let gt5 = comparison("page", CompareOp::Gt, MetaValue::Int(5));
+        assert!(provider.filter_offset(5, &gt5).is_none());
+        assert_eq!(
+            provider.filter_offset(15, &gt5).unwrap().get("page"),
+            Some(&MetaValue::Int(9))
+        );
+        assert!(provider.filter_offset(500, &gt5).is_none());
+    }
+
+    // ========================================================================
+    // Step 3.4 Tests: IndexedMetaProvider
+    // ========================================================================
+
+    fn build_indexed_provider() -> IndexedMetaProvider {
+        let mut regions = Vec::new();
+        for i in 0..10u64 {
+            let mut meta = MatchMetadata::new();
+            meta.insert("page", MetaValue::Int(i as i64));
+            meta.insert("chapter", MetaValue::Str(if i < 5 { "Intro".into() } else { "Appendix".into() }));
+            regions.push(MetaRegion::new(i * 10, i * 10 + 10, meta));
+        }
+        IndexedMetaProvider::new(regions)
+    }
+
+    fn pages_of(regions: &[&MetaRegion]) -> Vec<i64> {
+        regions
+            .iter()
+            .map(|r| match r.meta.get("page").unwrap() {
+                MetaValue::Int(i) => *i,
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_indexed_provider_eq_and_in() {
+        let provider = build_indexed_provider();
+
+        let eq = comparison("page", CompareOp::Eq, MetaValue::Int(3));
+        assert_eq!(pages_of(&provider.select_regions(&eq)), vec![3]);
+
+        let in_set = comparison("page", CompareOp::In(vec![MetaValue::Int(1), MetaValue::Int(8)]), MetaValue::Bool(true));
+        let mut pages = pages_of(&provider.select_regions(&in_set));
+        pages.sort_unstable();
+        assert_eq!(pages, vec![1, 8]);
+    }
+
+    #[test]
+    fn test_indexed_provider_ordering_and_exists() {
+        let provider = build_indexed_provider();
+
+        let gt7 = comparison("page", CompareOp::Gt, MetaValue::Int(7));
+        let mut pages = pages_of(&provider.select_regions(&gt7));
+        pages.sort_unstable();
+        assert_eq!(pages, vec![8, 9]);
+
+        assert_eq!(provider.select_regions(&Where::Exists("page".into())).len(), 10);
+        assert_eq!(provider.select_regions(&Where::Exists("missing".into())).len(), 0);
+    }
+
+    #[test]
+    fn test_indexed_provider_and_or_not() {
+        let provider = build_indexed_provider();
+
+        let gt2 = comparison("page", CompareOp::Gt, MetaValue::Int(2));
+        let lt5 = comparison("page", CompareOp::Lt, MetaValue::Int(5));
+        assert_eq!(
+            provider.select_regions(&Where::And(vec![gt2.clone(), lt5.clone()])).len(),
+            2 // pages 3, 4
+        );
+        assert_eq!(
+            provider.select_regions(&Where::Or(vec![gt2, lt5])).len(),
+            10 // every page is either < 5 or > 2
+        );
+
+        let appendix = comparison("chapter", CompareOp::Eq, MetaValue::Str("Appendix".into()));
+        assert_eq!(
+            provider.select_regions(&Where::Not(Box::new(appendix))).len(),
+            5 // the 5 "Intro" pages
+        );
+    }
+
+    #[test]
+    fn test_indexed_provider_contains_and_offset_lookup() {
+        let provider = build_indexed_provider();
+
+        let contains = comparison("chapter", CompareOp::Contains, MetaValue::Str("pend".into()));
+        assert_eq!(provider.select_regions(&contains).len(), 5);
+
+        assert_eq!(
+            provider.metadata_for_offset(35).unwrap().get("page"),
+            Some(&MetaValue::Int(3))
+        );
+        assert!(provider.metadata_for_offset(1000).is_none());
+        assert_eq!(provider.region_count(), 10);
+    }
+
+    #[test]
+    fn test_indexed_provider_empty_combinators_agree_with_where_eval() {
+        let provider = build_indexed_provider();
+
+        // `And(vec![])` is vacuously true (`Iterator::all` on empty), so
+        // it must match every region, just like `Where::eval` and
+        // `VecMetaProvider::filter_regions` do.
+        assert_eq!(provider.select_regions(&Where::And(vec![])).len(), 10);
+
+        // `Or(vec![])` is vacuously false (`Iterator::any` on empty).
+        assert_eq!(provider.select_regions(&Where::Or(vec![])).len(), 0);
+    }
+
+    // ========================================================================
+    // Step 3.5 Tests: FacetAccumulator
+    // ========================================================================
+
+    #[test]
+    fn test_facet_accumulator_categorical_and_numeric() {
+        let mut acc = FacetAccumulator::new(["page", "chapter"]);
+
+        for (page, chapter) in [(1, "Intro"), (2, "Intro"), (5, "Appendix")] {
+            let mut meta = MatchMetadata::new();
+            meta.insert("page", MetaValue::Int(page));
+            meta.insert("chapter", MetaValue::Str(chapter.into()));
+            acc.add(&meta);
+        }
+
+        let results = acc.finish();
+
+        let page = results.get("page").unwrap();
+        assert_eq!(page.counts.get(&FacetValue::Int(1)), Some(&1));
+        assert_eq!(page.counts.get(&FacetValue::Int(2)), Some(&1));
+        assert_eq!(page.missing, 0);
+        let stats = page.numeric.unwrap();
+        assert_eq!(stats.min, 1.0);
+        assert_eq!(stats.max, 5.0);
+        assert_eq!(stats.sum, 8.0);
+        assert_eq!(stats.count, 3);
+
+        let chapter = results.get("chapter").unwrap();
+        assert_eq!(chapter.counts.get(&FacetValue::Str("Intro".into())), Some(&2));
+        assert_eq!(chapter.counts.get(&FacetValue::Str("Appendix".into())), Some(&1));
+        assert!(chapter.numeric.is_none());
+    }
+
+    #[test]
+    fn test_facet_accumulator_missing_and_mixed_types() {
+        let mut acc = FacetAccumulator::new(["page"]);
+
+        let mut has_page = MatchMetadata::new();
+        has_page.insert("page", MetaValue::Int(1));
+        acc.add(&has_page);
+
+        // A string masquerading under the same key: bucketed
+        // categorically, but kept out of the numeric stats.
+        let mut string_page = MatchMetadata::new();
+        string_page.insert("page", MetaValue::Str("cover".into()));
+        acc.add(&string_page);
+
+        // No value for "page" at all.
+        acc.add(&MatchMetadata::new());
+
+        let results = acc.finish();
+        let page = results.get("page").unwrap();
+        assert_eq!(page.missing, 1);
+        assert_eq!(page.counts.get(&FacetValue::Int(1)), Some(&1));
+        assert_eq!(page.counts.get(&FacetValue::Str("cover".into())), Some(&1));
+        assert_eq!(page.numeric.unwrap().count, 1);
+    }
+
+    #[test]
+    fn test_facet_accumulator_add_offsets_via_provider() {
+        let mut meta1 = MatchMetadata::new();
+        meta1.insert("page", MetaValue::Int(1));
+        let mut meta2 = MatchMetadata::new();
+        meta2.insert("page", MetaValue::Int(2));
+
+        let provider = VecMetaProvider::new(vec![
+            MetaRegion { start: 0, end: 10, meta: meta1 },
+            MetaRegion { start: 10, end: 20, meta: meta2 },
+        ]);
+
+        let mut acc = FacetAccumulator::new(["page"]);
+        acc.add_offsets([5, 15, 500], &provider);
+
+        let results = acc.finish();
+        let page = results.get("page").unwrap();
+        assert_eq!(page.missing, 1); // offset 500 has no metadata
+        assert_eq!(page.numeric.unwrap().count, 2);
+    }
+
+    // ========================================================================
+    // Step 3.6 Tests: MetaSort / sort_offsets
+    // ========================================================================
+
+    #[test]
+    fn test_metavalue_cmp_total_groups_numeric_before_str_before_bool() {
+        assert_eq!(
+            MetaValue::Int(100).cmp_total(&MetaValue::Str("a".into())),
+            std::cmp::Ordering::Less
+        );
+        assert_eq!(
+            MetaValue::Str("z".into()).cmp_total(&MetaValue::Bool(false)),
+            std::cmp::Ordering::Less
+        );
+        assert_eq!(
+            MetaValue::Int(1).cmp_total(&MetaValue::Float(2.0)),
+            std::cmp::Ordering::Less
+        );
+        assert_eq!(
+            MetaValue::Str("a".into()).cmp_total(&MetaValue::Str("b".into())),
+            std::cmp::Ordering::Less
+        );
+    }
+
+    fn make_page_provider() -> VecMetaProvider {
+        let mut has_page_3 = MatchMetadata::new();
+        has_page_3.insert("page", MetaValue::Int(3));
+        let mut has_page_1 = MatchMetadata::new();
+        has_page_1.insert("page", MetaValue::Int(1));
+        let mut no_page = MatchMetadata::new();
+        no_page.insert("other", MetaValue::Bool(true));
+
+        VecMetaProvider::new(vec![
+            MetaRegion { start: 0, end: 10, meta: has_page_3 },
+            MetaRegion { start: 10, end: 20, meta: has_page_1 },
+            MetaRegion { start: 20, end: 30, meta: no_page },
+        ])
+    }
+
+    #[test]
+    fn test_sort_offsets_ascending_with_missing_last() {
+        let provider = make_page_provider();
+        let mut offsets = [25u64, 5, 15];
+        provider.sort_offsets(
+            &mut offsets,
+            &[MetaSort { key: "page".into(), order: SortOrder::Asc }],
+        );
+        // page=1 (offset 15), page=3 (offset 5), then missing (offset 25).
+        assert_eq!(offsets, [15, 5, 25]);
+    }
+
+    #[test]
+    fn test_sort_offsets_descending_keeps_missing_last() {
+        let provider = make_page_provider();
+        let mut offsets = [25u64, 5, 15];
+        provider.sort_offsets(
+            &mut offsets,
+            &[MetaSort { key: "page".into(), order: SortOrder::Desc }],
+        );
+        // Reversed among present values (page=3 then page=1), but the
+        // missing offset still sorts last.
+        assert_eq!(offsets, [5, 15, 25]);
+    }
+
+    #[test]
+    fn test_sort_offsets_multi_key_tie_break() {
+        let mut meta_a = MatchMetadata::new();
+        meta_a.insert("page", MetaValue::Int(1));
+        meta_a.insert("chapter", MetaValue::Str("Zed".into()));
+        let mut meta_b = MatchMetadata::new();
+        meta_b.insert("page", MetaValue::Int(1));
+        meta_b.insert("chapter", MetaValue::Str("Abc".into()));
+
+        let provider = VecMetaProvider::new(vec![
+            MetaRegion { start: 0, end: 10, meta: meta_a },
+            MetaRegion { start: 10, end: 20, meta: meta_b },
+        ]);
+
+        let mut offsets = [5u64, 15];
+        provider.sort_offsets(
+            &mut offsets,
+            &[
+                MetaSort { key: "page".into(), order: SortOrder::Asc },
+                MetaSort { key: "chapter".into(), order: SortOrder::Asc },
+            ],
+        );
+        // Tied on page=1, broken by chapter: "Abc" (offset 15) before "Zed" (offset 5).
+        assert_eq!(offsets, [15, 5]);
+    }
 }
@@ -1,4 +1,5 @@
-use crate::{MatchMetadata, MetaRegion};
+use crate::sort::compare_by_sorts;
+use crate::{MatchMetadata, MetaRegion, MetaSort, Where};
 
 /// Trait for providing metadata based on byte offsets.
 ///
@@ -13,6 +14,21 @@ pub trait MetadataProvider: Send + Sync {
     ///
     /// Returns `None` if no metadata exists for the given offset.
     fn metadata_for_offset(&self, offset: u64) -> Option<&MatchMetadata>;
+
+    /// Sorts `offsets` in place by one or more metadata keys, giving
+    /// deterministic ordering of matches by document structure rather
+    /// than byte offset.
+    ///
+    /// Each [`MetaSort`] resolves its key's value to a group — numeric,
+    /// then string, then boolean, then "no value" — via
+    /// [`MetaValue::cmp_total`](crate::MetaValue::cmp_total); offsets
+    /// missing the key always sort last, regardless of sort direction.
+    /// Later sorts in `sorts` only break ties left by earlier ones.
+    fn sort_offsets(&self, offsets: &mut [u64], sorts: &[MetaSort]) {
+        offsets.sort_by(|&a, &b| {
+            compare_by_sorts(self.metadata_for_offset(a), self.metadata_for_offset(b), sorts)
+        });
+    }
 }
 
 /// A simple vector-based metadata provider.
@@ -56,6 +72,86 @@ impl VecMetaProvider {
     pub fn regions(&self) -> &[MetaRegion] {
         &self.regions
     }
+
+    /// Returns the indices (into [`VecMetaProvider::regions`]) of every
+    /// region whose metadata satisfies `pred`, in ascending order.
+    ///
+    /// `And`/`Or` combinators are evaluated by merging the sorted index
+    /// lists of their children rather than re-evaluating `pred` per
+    /// region, so a predicate need only be evaluated once per leaf per
+    /// region. An empty `And` matches every region (vacuously true, as
+    /// `Where::eval` agrees via `Iterator::all`); an empty `Or` matches
+    /// none (vacuously false, via `Iterator::any`).
+    pub fn filter_regions(&self, pred: &Where) -> Vec<usize> {
+        match pred {
+            Where::And(children) => children
+                .iter()
+                .map(|child| self.filter_regions(child))
+                .reduce(|acc, next| intersect_sorted(&acc, &next))
+                .unwrap_or_else(|| (0..self.regions.len()).collect()),
+            Where::Or(children) => children
+                .iter()
+                .map(|child| self.filter_regions(child))
+                .fold(Vec::new(), |acc, next| union_sorted(&acc, &next)),
+            Where::Comparison { .. } | Where::Exists(_) | Where::Not(_) => self
+                .regions
+                .iter()
+                .enumerate()
+                .filter(|(_, r)| pred.eval(&r.meta))
+                .map(|(i, _)| i)
+                .collect(),
+        }
+    }
+
+    /// Looks up the metadata at `offset` and returns it only if it
+    /// satisfies `filter`.
+    pub fn filter_offset(&self, offset: u64, filter: &Where) -> Option<&MatchMetadata> {
+        self.metadata_for_offset(offset).filter(|meta| filter.eval(meta))
+    }
+}
+
+/// Merges two sorted, deduplicated index lists into their intersection.
+fn intersect_sorted(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let mut out = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Equal => {
+                out.push(a[i]);
+                i += 1;
+                j += 1;
+            }
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+        }
+    }
+    out
+}
+
+/// Merges two sorted, deduplicated index lists into their union.
+fn union_sorted(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let mut out = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Equal => {
+                out.push(a[i]);
+                i += 1;
+                j += 1;
+            }
+            std::cmp::Ordering::Less => {
+                out.push(a[i]);
+                i += 1;
+            }
+            std::cmp::Ordering::Greater => {
+                out.push(b[j]);
+                j += 1;
+            }
+        }
+    }
+    out.extend_from_slice(&a[i..]);
+    out.extend_from_slice(&b[j..]);
+    out
 }
 
 impl MetadataProvider for VecMetaProvider {
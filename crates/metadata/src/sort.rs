@@ -0,0 +1,59 @@
+use crate::{MatchMetadata, MetaValue};
+
+/// Sort direction for a [`MetaSort`] key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// A single query-time sort key, following MeiliSearch's `Asc`/`Desc`
+/// sort-at-query-time semantics: a metadata key plus the direction to
+/// order its values in.
+///
+/// Use a `Vec<MetaSort>` for stable multi-key tie-breaking, e.g. sort by
+/// `page` then `chapter`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetaSort {
+    pub key: String,
+    pub order: SortOrder,
+}
+
+/// Orders two (possibly absent) values for one sort key.
+///
+/// Numeric and string values are ordered (and reversed for `Desc`) by
+/// [`MetaValue::cmp_total`]; a missing value always sorts last,
+/// regardless of direction.
+fn compare_values(a: Option<&MetaValue>, b: Option<&MetaValue>, order: SortOrder) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    let ordering = match (a, b) {
+        (Some(a), Some(b)) => a.cmp_total(b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    };
+    match order {
+        SortOrder::Asc => ordering,
+        SortOrder::Desc if a.is_none() || b.is_none() => ordering,
+        SortOrder::Desc => ordering.reverse(),
+    }
+}
+
+/// Orders two (possibly absent) metadata sets by `sorts`, falling
+/// through to later keys on a tie.
+pub(crate) fn compare_by_sorts(
+    meta_a: Option<&MatchMetadata>,
+    meta_b: Option<&MatchMetadata>,
+    sorts: &[MetaSort],
+) -> std::cmp::Ordering {
+    for sort in sorts {
+        let a = meta_a.and_then(|meta| meta.get(&sort.key));
+        let b = meta_b.and_then(|meta| meta.get(&sort.key));
+        let ordering = compare_values(a, b, sort.order);
+        if ordering != std::cmp::Ordering::Equal {
+            return ordering;
+        }
+    }
+    std::cmp::Ordering::Equal
+}
@@ -58,3 +58,56 @@ impl From<bool> for MetaValue {
         MetaValue::Bool(b)
     }
 }
+
+impl MetaValue {
+    /// Returns this value as `f64` if it is numeric (`Int` or `Float`),
+    /// promoting `Int` by conversion.
+    fn as_f64(&self) -> Option<f64> {
+        match *self {
+            MetaValue::Int(i) => Some(i as f64),
+            MetaValue::Float(f) => Some(f),
+            _ => None,
+        }
+    }
+
+    /// Compares two values for ordering.
+    ///
+    /// `Int` and `Float` compare numerically, promoting `Int` to `f64`;
+    /// `Str` compares lexicographically; `Bool` orders `false < true`.
+    /// Values of incompatible types (e.g. `Int` vs `Str`) are
+    /// incomparable, and this returns `None`.
+    pub fn compare(&self, other: &MetaValue) -> Option<std::cmp::Ordering> {
+        if let (Some(a), Some(b)) = (self.as_f64(), other.as_f64()) {
+            return a.partial_cmp(&b);
+        }
+        match (self, other) {
+            (MetaValue::Str(a), MetaValue::Str(b)) => Some(a.cmp(b)),
+            (MetaValue::Bool(a), MetaValue::Bool(b)) => Some(a.cmp(b)),
+            _ => None,
+        }
+    }
+
+    /// Orders any two values, even of different types, for query-time
+    /// sorting (see [`MetaSort`](crate::MetaSort)).
+    ///
+    /// Unlike [`MetaValue::compare`], which returns `None` for
+    /// incomparable types, this always returns an `Ordering` by grouping
+    /// values: numeric (`Int`/`Float`, by `f64` value) sorts before
+    /// `Str` (lexicographically), which sorts before `Bool`
+    /// (`false` before `true`).
+    pub fn cmp_total(&self, other: &MetaValue) -> std::cmp::Ordering {
+        match (self.as_f64(), other.as_f64()) {
+            (Some(a), Some(b)) => return a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal),
+            (Some(_), None) => return std::cmp::Ordering::Less,
+            (None, Some(_)) => return std::cmp::Ordering::Greater,
+            (None, None) => {}
+        }
+        match (self, other) {
+            (MetaValue::Str(a), MetaValue::Str(b)) => a.cmp(b),
+            (MetaValue::Str(_), MetaValue::Bool(_)) => std::cmp::Ordering::Less,
+            (MetaValue::Bool(_), MetaValue::Str(_)) => std::cmp::Ordering::Greater,
+            (MetaValue::Bool(a), MetaValue::Bool(b)) => a.cmp(b),
+            _ => unreachable!("numeric pairs are already handled above"),
+        }
+    }
+}
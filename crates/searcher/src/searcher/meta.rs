@@ -3,18 +3,68 @@ Metadata-aware search strategies.
 
 This module provides search strategy implementations that support metadata
 providers. These strategies are similar to their non-metadata counterparts
-but additionally look up and attach metadata to matches and context lines.
+but additionally look up and attach metadata to matches and context lines:
+`SliceByLineWithMeta` resolves metadata separately for each before/after
+context line it emits, so a context line on a different PDF page than its
+match carries that page's metadata rather than the match's.
 */
 
+use std::collections::VecDeque;
+use std::io::Read;
+
 use grep_matcher::Matcher;
-use grep_metadata::MetadataProvider;
+use grep_metadata::{MatchMetadata, MetadataProvider, Where};
 
 use crate::{
+    line_buffer::LineBufferReader,
     lines::LineIter,
     searcher::Searcher,
-    sink::{SinkError, SinkFinish, SinkMatch, SinkMatchWithMeta, SinkWithMeta},
+    sink::{
+        Sink, SinkContext, SinkContextKind, SinkError, SinkFinish, SinkMatch,
+        SinkMatchWithMeta, SinkWithMeta,
+    },
 };
 
+/// A line of before/after context paired with metadata resolved for that
+/// specific line.
+///
+/// A context line can carry different metadata than the match it
+/// surrounds: a context line that sits on the previous PDF page carries
+/// that page's metadata, not the match's.
+pub struct ContextLineWithMeta<'s, 'm> {
+    pub base: SinkContext<'s>,
+    pub metadata: Option<&'m MatchMetadata>,
+}
+
+/// A sink that additionally receives metadata-aware context lines.
+///
+/// Parallels [`SinkWithMeta`] the way `Sink::context` parallels
+/// `Sink::matched`. The default implementation ignores context lines,
+/// so existing `SinkWithMeta` implementors only need an empty `impl` to
+/// keep compiling.
+pub trait SinkContextWithMeta: Sink {
+    /// Called for each before/after context line surrounding a match.
+    ///
+    /// Returning `Ok(false)` stops the search, just like
+    /// `matched_with_meta`.
+    fn context_with_meta(
+        &mut self,
+        _searcher: &Searcher,
+        _context: &ContextLineWithMeta<'_, '_>,
+    ) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+}
+
+/// A single buffered line, held either as pending before-context or while
+/// deciding whether it becomes a match.
+struct BufferedLine<'s> {
+    bytes: &'s [u8],
+    absolute_byte_offset: u64,
+    line_number: Option<u64>,
+    line_index: u64,
+}
+
 /// A metadata-aware line-by-line search strategy for slices.
 ///
 /// This is similar to `SliceByLine` but adds metadata support.
@@ -23,17 +73,25 @@ pub(crate) struct SliceByLineWithMeta<'s, 'm, M, S> {
     matcher: M,
     slice: &'s [u8],
     provider: Option<&'m dyn MetadataProvider>,
+    filter: Option<&'m Where>,
     sink: S,
     absolute_byte_offset: u64,
     line_number: Option<u64>,
+    /// A 1-based counter over every line seen so far, maintained
+    /// regardless of whether `searcher.config.line_number` is turned on.
+    /// Used only to detect a gap between consecutive context/match
+    /// blocks so a separator can be emitted between them.
+    current_line_index: u64,
+    last_emitted_line_index: Option<u64>,
 }
 
-impl<'s, 'm, M: Matcher, S: SinkWithMeta> SliceByLineWithMeta<'s, 'm, M, S> {
+impl<'s, 'm, M: Matcher, S: SinkWithMeta + SinkContextWithMeta> SliceByLineWithMeta<'s, 'm, M, S> {
     pub(crate) fn new(
         searcher: &'s Searcher,
         matcher: M,
         slice: &'s [u8],
         provider: Option<&'m dyn MetadataProvider>,
+        filter: Option<&'m Where>,
         sink: S,
     ) -> Self {
         let line_number = if searcher.config.line_number {
@@ -47,9 +105,12 @@ impl<'s, 'm, M: Matcher, S: SinkWithMeta> SliceByLineWithMeta<'s, 'm, M, S> {
             matcher,
             slice,
             provider,
+            filter,
             sink,
             absolute_byte_offset: 0,
             line_number,
+            current_line_index: 0,
+            last_emitted_line_index: None,
         }
     }
 
@@ -60,10 +121,17 @@ impl<'s, 'm, M: Matcher, S: SinkWithMeta> SliceByLineWithMeta<'s, 'm, M, S> {
         self.sink.begin(self.searcher)?;
 
         let line_term = self.searcher.line_terminator();
+        let before_context = self.searcher.config.before_context;
+        let after_context = self.searcher.config.after_context;
+        let mut before_buf: VecDeque<BufferedLine<'s>> = VecDeque::with_capacity(before_context);
+        let mut after_remaining: usize = 0;
+
         let mut line_iter = LineIter::new(line_term.as_byte(), self.slice);
 
         while let Some(line) = line_iter.next() {
             let line_offset = self.absolute_byte_offset;
+            self.current_line_index += 1;
+            let line_index = self.current_line_index;
 
             // Strip line terminator for matching (LineIter includes it)
             let line_without_term = if line.ends_with(&[line_term.as_byte()]) {
@@ -87,6 +155,56 @@ impl<'s, 'm, M: Matcher, S: SinkWithMeta> SliceByLineWithMeta<'s, 'm, M, S> {
                 let metadata = self.provider
                     .and_then(|p| p.metadata_for_offset(line_offset));
 
+                // A predicate is evaluated against the resolved metadata;
+                // a match that has no metadata to check against a
+                // predicate is skipped rather than emitted.
+                let passes_filter = match self.filter {
+                    Some(filter) => metadata.map_or(false, |m| filter.eval(m)),
+                    None => true,
+                };
+                if !passes_filter {
+                    // A line that fails the filter is never itself a
+                    // match, but it still occupies a slot in whatever
+                    // context window it falls inside. If it falls inside
+                    // an open after-context window, spend that budget
+                    // here just like the `after_remaining > 0` branch
+                    // below does -- otherwise the window would silently
+                    // stretch past `after_context` lines.
+                    if after_remaining > 0 {
+                        after_remaining -= 1;
+                    }
+                    before_buf.push_back(BufferedLine {
+                        bytes: line,
+                        absolute_byte_offset: line_offset,
+                        line_number: self.line_number,
+                        line_index,
+                    });
+                    while before_buf.len() > before_context {
+                        before_buf.pop_front();
+                    }
+                    self.advance(line);
+                    continue;
+                }
+
+                // Flush buffered before-context, emitting a separator
+                // first if it isn't contiguous with what was last sent.
+                let first_index = before_buf.front().map(|b| b.line_index).unwrap_or(line_index);
+                if !self.maybe_context_break(first_index)? {
+                    return Ok(());
+                }
+                while let Some(buffered) = before_buf.pop_front() {
+                    if !self.emit_context(
+                        line_term,
+                        buffered.bytes,
+                        buffered.absolute_byte_offset,
+                        buffered.line_number,
+                        buffered.line_index,
+                        SinkContextKind::Before,
+                    )? {
+                        return Ok(());
+                    }
+                }
+
                 // Create SinkMatch
                 let base = SinkMatch {
                     line_term,
@@ -102,13 +220,33 @@ impl<'s, 'm, M: Matcher, S: SinkWithMeta> SliceByLineWithMeta<'s, 'm, M, S> {
                 if !self.sink.matched_with_meta(self.searcher, &mat)? {
                     return Ok(());
                 }
+                self.last_emitted_line_index = Some(line_index);
+                after_remaining = after_context;
+            } else if after_remaining > 0 {
+                after_remaining -= 1;
+                if !self.emit_context(
+                    line_term,
+                    line,
+                    line_offset,
+                    self.line_number,
+                    line_index,
+                    SinkContextKind::After,
+                )? {
+                    return Ok(());
+                }
+            } else if before_context > 0 {
+                before_buf.push_back(BufferedLine {
+                    bytes: line,
+                    absolute_byte_offset: line_offset,
+                    line_number: self.line_number,
+                    line_index,
+                });
+                while before_buf.len() > before_context {
+                    before_buf.pop_front();
+                }
             }
 
-            // Update position tracking
-            self.absolute_byte_offset += line.len() as u64;
-            if let Some(ref mut line_num) = self.line_number {
-                *line_num += 1;
-            }
+            self.advance(line);
         }
 
         self.sink.finish(
@@ -121,6 +259,50 @@ impl<'s, 'm, M: Matcher, S: SinkWithMeta> SliceByLineWithMeta<'s, 'm, M, S> {
 
         Ok(())
     }
+
+    /// Advances the running byte offset and display line number past `line`.
+    fn advance(&mut self, line: &[u8]) {
+        self.absolute_byte_offset += line.len() as u64;
+        if let Some(ref mut line_num) = self.line_number {
+            *line_num += 1;
+        }
+    }
+
+    /// Emits one context line with its own resolved metadata.
+    ///
+    /// Returns `Ok(false)` if the sink asked the search to stop.
+    fn emit_context(
+        &mut self,
+        line_term: crate::LineTerminator,
+        bytes: &'s [u8],
+        absolute_byte_offset: u64,
+        line_number: Option<u64>,
+        line_index: u64,
+        kind: SinkContextKind,
+    ) -> Result<bool, S::Error> {
+        let metadata =
+            self.provider.and_then(|p| p.metadata_for_offset(absolute_byte_offset));
+        let base = SinkContext { line_term, bytes, absolute_byte_offset, line_number, kind };
+        let context = ContextLineWithMeta { base, metadata };
+        let keep_going = self.sink.context_with_meta(self.searcher, &context)?;
+        if keep_going {
+            self.last_emitted_line_index = Some(line_index);
+        }
+        Ok(keep_going)
+    }
+
+    /// Emits a context separator if the next context/match line isn't
+    /// contiguous with the last line actually sent to the sink.
+    ///
+    /// Returns `Ok(false)` if the sink asked the search to stop.
+    fn maybe_context_break(&mut self, next_line_index: u64) -> Result<bool, S::Error> {
+        if let Some(last) = self.last_emitted_line_index {
+            if next_line_index > last + 1 {
+                return self.sink.context_break(self.searcher);
+            }
+        }
+        Ok(true)
+    }
 }
 
 /// A metadata-aware multi-line search strategy for slices.
@@ -131,8 +313,15 @@ pub(crate) struct MultiLineWithMeta<'s, 'm, M, S> {
     matcher: M,
     slice: &'s [u8],
     provider: Option<&'m dyn MetadataProvider>,
+    filter: Option<&'m Where>,
     sink: S,
     absolute_byte_offset: u64,
+    /// The number of line terminators counted in `slice[..lines_counted_upto]`.
+    /// Incrementally extended as the scan advances so that deriving a
+    /// match's 1-based starting line number never rescans from the start
+    /// of the slice.
+    lines_seen: u64,
+    lines_counted_upto: usize,
 }
 
 impl<'s, 'm, M: Matcher, S: SinkWithMeta> MultiLineWithMeta<'s, 'm, M, S> {
@@ -141,6 +330,7 @@ impl<'s, 'm, M: Matcher, S: SinkWithMeta> MultiLineWithMeta<'s, 'm, M, S> {
         matcher: M,
         slice: &'s [u8],
         provider: Option<&'m dyn MetadataProvider>,
+        filter: Option<&'m Where>,
         sink: S,
     ) -> Self {
         MultiLineWithMeta {
@@ -148,9 +338,44 @@ impl<'s, 'm, M: Matcher, S: SinkWithMeta> MultiLineWithMeta<'s, 'm, M, S> {
             matcher,
             slice,
             provider,
+            filter,
             sink,
             absolute_byte_offset: 0,
+            lines_seen: 0,
+            lines_counted_upto: 0,
+        }
+    }
+
+    /// Expands `[start, end)` outward to the boundaries of every line it
+    /// touches: back to one past the previous line terminator (or the
+    /// start of the slice), and forward through the terminator that ends
+    /// the last touched line (or the end of the slice).
+    fn expand_to_line_boundaries(&self, start: usize, end: usize) -> (usize, usize) {
+        let term = self.searcher.line_terminator().as_byte();
+        let expanded_start = match self.slice[..start].iter().rposition(|&b| b == term) {
+            Some(pos) => pos + 1,
+            None => 0,
+        };
+        let expanded_end = match self.slice[end..].iter().position(|&b| b == term) {
+            Some(pos) => end + pos + 1,
+            None => self.slice.len(),
+        };
+        (expanded_start, expanded_end)
+    }
+
+    /// Returns the 1-based line number of the line starting at
+    /// `line_start`, advancing the incremental terminator tally up to
+    /// that point.
+    fn line_number_at(&mut self, line_start: usize) -> u64 {
+        let term = self.searcher.line_terminator().as_byte();
+        if line_start > self.lines_counted_upto {
+            self.lines_seen += self.slice[self.lines_counted_upto..line_start]
+                .iter()
+                .filter(|&&b| b == term)
+                .count() as u64;
+            self.lines_counted_upto = line_start;
         }
+        self.lines_seen + 1
     }
 
     pub(crate) fn run(mut self) -> Result<(), S::Error>
@@ -168,19 +393,45 @@ impl<'s, 'm, M: Matcher, S: SinkWithMeta> MultiLineWithMeta<'s, 'm, M, S> {
                     let match_offset = offset + mat.start();
                     let match_end = offset + mat.end();
 
-                    // Look up metadata
+                    // Expand the match out to the full line(s) it
+                    // touches; ripgrep's multi-line engine reports whole
+                    // lines, not just the matched substring.
+                    let (line_start, line_end) =
+                        self.expand_to_line_boundaries(match_offset, match_end);
+
+                    // Metadata is resolved at the expanded start so the
+                    // attached page/chapter corresponds to where the
+                    // match actually begins.
                     let metadata = self.provider
-                        .and_then(|p| p.metadata_for_offset(match_offset as u64));
+                        .and_then(|p| p.metadata_for_offset(line_start as u64));
+
+                    // Skip matches whose metadata fails the predicate
+                    // instead of emitting them.
+                    if let Some(filter) = self.filter {
+                        let passes = metadata.map_or(false, |m| filter.eval(m));
+                        if !passes {
+                            offset = line_end.max(match_end);
+                            if offset == match_offset {
+                                offset += 1;
+                            }
+                            continue;
+                        }
+                    }
+
+                    let line_number = if self.searcher.config.line_number {
+                        Some(self.line_number_at(line_start))
+                    } else {
+                        None
+                    };
 
-                    // Create SinkMatch (simplified - doesn't handle line numbers properly)
                     let line_term = self.searcher.line_terminator();
                     let base = SinkMatch {
                         line_term,
-                        bytes: &self.slice[match_offset..match_end],
-                        absolute_byte_offset: match_offset as u64,
-                        line_number: None, // Multi-line search doesn't track line numbers in this simple version
+                        bytes: &self.slice[line_start..line_end],
+                        absolute_byte_offset: line_start as u64,
+                        line_number,
                         buffer: self.slice,
-                        bytes_range_in_buffer: match_offset..match_end,
+                        bytes_range_in_buffer: line_start..line_end,
                     };
 
                     let sink_mat = SinkMatchWithMeta { base, metadata };
@@ -189,7 +440,11 @@ impl<'s, 'm, M: Matcher, S: SinkWithMeta> MultiLineWithMeta<'s, 'm, M, S> {
                         break;
                     }
 
-                    offset = match_end;
+                    // Resume after the expanded line, not just the raw
+                    // match, so a second raw match later on the same
+                    // physical line doesn't get rediscovered and the
+                    // already-reported line emitted a second time.
+                    offset = line_end.max(match_end);
                     if offset == match_offset {
                         // Avoid infinite loop on zero-width matches
                         offset += 1;
@@ -211,3 +466,146 @@ impl<'s, 'm, M: Matcher, S: SinkWithMeta> MultiLineWithMeta<'s, 'm, M, S> {
         Ok(())
     }
 }
+
+/// A metadata-aware line-by-line search strategy for readers.
+///
+/// This is similar to `SliceByLineWithMeta`, but is driven by the
+/// searcher's incremental line buffer instead of requiring the whole
+/// haystack up front as a single `&[u8]`. The line buffer already
+/// guarantees that the bytes handed back by a single `fill()` end on a
+/// line boundary (except for the final, possibly-partial chunk at EOF),
+/// so offsets only need to be anchored against the buffer's running
+/// `absolute_byte_offset` -- the same offset that's persisted across
+/// refills -- rather than recomputed per call.
+pub(crate) struct ReaderByLineWithMeta<'s, 'm, M, R, S> {
+    searcher: &'s Searcher,
+    matcher: M,
+    read_from: R,
+    provider: Option<&'m dyn MetadataProvider>,
+    filter: Option<&'m Where>,
+    sink: S,
+    line_number: Option<u64>,
+}
+
+impl<'s, 'm, M: Matcher, R: Read, S: SinkWithMeta> ReaderByLineWithMeta<'s, 'm, M, R, S> {
+    pub(crate) fn new(
+        searcher: &'s Searcher,
+        matcher: M,
+        read_from: R,
+        provider: Option<&'m dyn MetadataProvider>,
+        filter: Option<&'m Where>,
+        sink: S,
+    ) -> Self {
+        let line_number = if searcher.config.line_number {
+            Some(1)
+        } else {
+            None
+        };
+
+        ReaderByLineWithMeta {
+            searcher,
+            matcher,
+            read_from,
+            provider,
+            filter,
+            sink,
+            line_number,
+        }
+    }
+
+    pub(crate) fn run(mut self) -> Result<(), S::Error>
+    where
+        S::Error: From<<M as Matcher>::Error> + From<std::io::Error>,
+    {
+        self.sink.begin(self.searcher)?;
+
+        let line_term = self.searcher.line_terminator();
+        let mut line_buffer = self.searcher.line_buffer();
+        let mut reader = LineBufferReader::new(&mut self.read_from, &mut line_buffer);
+
+        while reader.fill().map_err(S::Error::from)? {
+            // The offset of the first byte in the currently buffered
+            // chunk, relative to the start of the whole stream. This is
+            // the running, cross-refill offset that keeps offsets correct
+            // even once earlier buffer contents have been discarded.
+            let buffer_start = reader.absolute_byte_offset();
+            let buf = reader.buffer();
+
+            let mut line_iter = LineIter::new(line_term.as_byte(), buf);
+            let mut local_offset: usize = 0;
+
+            while let Some(line) = line_iter.next() {
+                // The match's true global start offset, not the
+                // buffer-local one -- this is what must be handed to the
+                // metadata provider so a line straddling a previous
+                // refill is still resolved correctly.
+                let line_offset = buffer_start + local_offset as u64;
+
+                let line_without_term = if line.ends_with(&[line_term.as_byte()]) {
+                    &line[..line.len() - 1]
+                } else {
+                    line
+                };
+
+                let is_match = self.matcher.is_match(line_without_term)?;
+                let should_report = if self.searcher.config.invert_match {
+                    !is_match
+                } else {
+                    is_match
+                };
+
+                if should_report {
+                    let metadata =
+                        self.provider.and_then(|p| p.metadata_for_offset(line_offset));
+
+                    let passes_filter = match self.filter {
+                        Some(filter) => metadata.map_or(false, |m| filter.eval(m)),
+                        None => true,
+                    };
+
+                    if passes_filter {
+                        let base = SinkMatch {
+                            line_term,
+                            bytes: line,
+                            absolute_byte_offset: line_offset,
+                            line_number: self.line_number,
+                            buffer: buf,
+                            bytes_range_in_buffer: local_offset..local_offset + line.len(),
+                        };
+                        let mat = SinkMatchWithMeta { base, metadata };
+
+                        if !self.sink.matched_with_meta(self.searcher, &mat)? {
+                            return Ok(());
+                        }
+                    }
+                }
+
+                local_offset += line.len();
+                if let Some(ref mut line_num) = self.line_number {
+                    *line_num += 1;
+                }
+            }
+
+            let consumed = buf.len();
+            reader.consume(consumed);
+
+            // Checked after processing `buf`, not before: a "quit on
+            // binary" line buffer truncates `buf` to end exactly at the
+            // binary byte, so any matches in that final, valid prefix
+            // must still be reported before the search stops.
+            if reader.binary_byte_offset().is_some() {
+                break;
+            }
+        }
+
+        self.sink.finish(
+            self.searcher,
+            &SinkFinish {
+                byte_count: reader.absolute_byte_offset(),
+                binary_byte_offset: reader.binary_byte_offset(),
+            },
+        )?;
+
+        Ok(())
+    }
+}
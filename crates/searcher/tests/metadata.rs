@@ -12,7 +12,8 @@ use std::io;
 use grep_regex::RegexMatcher;
 use grep_metadata::{MatchMetadata, MetaRegion, MetaValue, VecMetaProvider};
 use grep_searcher::{
-    Searcher, Sink, SinkError, SinkMatch, SinkMatchWithMeta, SinkWithMeta,
+    Searcher, Sink, SinkContextWithMeta, SinkError, SinkMatch, SinkMatchWithMeta,
+    SinkWithMeta,
 };
 
 /// A test sink that captures matches along with their metadata.
@@ -53,6 +54,8 @@ impl SinkWithMeta for MetadataCaptureSink {
     }
 }
 
+impl SinkContextWithMeta for MetadataCaptureSink {}
+
 #[test]
 fn test_search_with_metadata_basic() {
     // Haystack with two lines
@@ -131,6 +134,8 @@ fn test_search_without_metadata_provider() {
         }
     }
 
+    impl SinkContextWithMeta for NoMetaSink {}
+
     let mut sink = NoMetaSink {
         received_metadata: true,
     };
@@ -199,6 +204,8 @@ fn test_multiple_metadata_fields() {
         }
     }
 
+    impl SinkContextWithMeta for MultiFieldSink {}
+
     let mut sink = MultiFieldSink {
         page: None,
         chapter: None,
@@ -263,3 +270,297 @@ fn test_metadata_at_region_boundaries() {
     // Should find matches at different offsets (one per line)
     assert_eq!(sink.matches.len(), 3, "Expected exactly 3 matches (one per line)");
 }
+
+#[test]
+fn test_context_lines_carry_their_own_metadata() {
+    use grep_searcher::{ContextLineWithMeta, SinkContextKind};
+
+    // Page boundary falls between the context line and the match, so
+    // each should be tagged with a different page.
+    let haystack = b"before\nMATCH\nafter\n";
+    //                0-6   7-12   13-18
+
+    let mut page1 = MatchMetadata::new();
+    page1.insert("page", MetaValue::Int(1));
+    let mut page2 = MatchMetadata::new();
+    page2.insert("page", MetaValue::Int(2));
+
+    let regions = vec![
+        MetaRegion { start: 0, end: 7, meta: page1 },
+        MetaRegion { start: 7, end: 19, meta: page2 },
+    ];
+    let provider = VecMetaProvider::new(regions);
+
+    struct ContextCaptureSink {
+        before: Vec<Option<i64>>,
+        after: Vec<Option<i64>>,
+    }
+
+    fn page_of(meta: Option<&MatchMetadata>) -> Option<i64> {
+        meta.and_then(|m| m.get("page")).and_then(|v| match v {
+            MetaValue::Int(i) => Some(*i),
+            _ => None,
+        })
+    }
+
+    impl Sink for ContextCaptureSink {
+        type Error = io::Error;
+        fn matched(&mut self, _: &Searcher, _: &SinkMatch<'_>) -> Result<bool, Self::Error> {
+            panic!("matched() should not be called");
+        }
+    }
+
+    impl SinkWithMeta for ContextCaptureSink {
+        fn matched_with_meta(
+            &mut self,
+            _: &Searcher,
+            _: &SinkMatchWithMeta<'_, '_>,
+        ) -> Result<bool, Self::Error> {
+            Ok(true)
+        }
+    }
+
+    impl SinkContextWithMeta for ContextCaptureSink {
+        fn context_with_meta(
+            &mut self,
+            _: &Searcher,
+            context: &ContextLineWithMeta<'_, '_>,
+        ) -> Result<bool, Self::Error> {
+            match context.base.kind() {
+                SinkContextKind::Before => self.before.push(page_of(context.metadata)),
+                SinkContextKind::After => self.after.push(page_of(context.metadata)),
+                SinkContextKind::Other => {}
+            }
+            Ok(true)
+        }
+    }
+
+    let mut sink = ContextCaptureSink { before: Vec::new(), after: Vec::new() };
+    let matcher = RegexMatcher::new("MATCH").unwrap();
+    let mut searcher = grep_searcher::SearcherBuilder::new()
+        .before_context(1)
+        .after_context(1)
+        .build();
+
+    searcher
+        .search_slice_with_metadata(matcher, haystack, Some(&provider), &mut sink)
+        .unwrap();
+
+    assert_eq!(sink.before, vec![Some(1)], "before-context line is on page 1");
+    assert_eq!(sink.after, vec![Some(2)], "after-context line is on page 2");
+}
+
+#[test]
+fn test_filter_does_not_stretch_after_context_window() {
+    use grep_metadata::{CompareOp, Where};
+    use grep_searcher::{ContextLineWithMeta, SinkContextKind};
+
+    // "skip" fails the filter (page 2 != 1) and falls inside the open
+    // after-context window opened by "MATCH". It must still spend one
+    // slot of that window rather than being treated as free before-
+    // context for the next match, or the after-context would silently
+    // stretch past the configured `after_context` count.
+    let haystack = b"MATCH\nskip\nafter\nafter2\n";
+    //                0-5   6-10  11-16  17-23
+
+    let mut page1 = MatchMetadata::new();
+    page1.insert("page", MetaValue::Int(1));
+    let mut page2 = MatchMetadata::new();
+    page2.insert("page", MetaValue::Int(2));
+
+    let regions = vec![
+        MetaRegion { start: 0, end: 6, meta: page1.clone() },
+        MetaRegion { start: 6, end: 11, meta: page2 },
+        MetaRegion { start: 11, end: haystack.len() as u64, meta: page1 },
+    ];
+    let provider = VecMetaProvider::new(regions);
+    let filter = Where::Comparison {
+        key: "page".into(),
+        op: CompareOp::Eq,
+        value: MetaValue::Int(1),
+    };
+
+    struct ContextCaptureSink {
+        matched: Vec<Vec<u8>>,
+        after: Vec<Vec<u8>>,
+    }
+
+    impl Sink for ContextCaptureSink {
+        type Error = io::Error;
+        fn matched(&mut self, _: &Searcher, _: &SinkMatch<'_>) -> Result<bool, Self::Error> {
+            panic!("matched() should not be called");
+        }
+    }
+
+    impl SinkWithMeta for ContextCaptureSink {
+        fn matched_with_meta(
+            &mut self,
+            _: &Searcher,
+            mat: &SinkMatchWithMeta<'_, '_>,
+        ) -> Result<bool, Self::Error> {
+            self.matched.push(mat.base.bytes().to_vec());
+            Ok(true)
+        }
+    }
+
+    impl SinkContextWithMeta for ContextCaptureSink {
+        fn context_with_meta(
+            &mut self,
+            _: &Searcher,
+            context: &ContextLineWithMeta<'_, '_>,
+        ) -> Result<bool, Self::Error> {
+            if let SinkContextKind::After = context.base.kind() {
+                self.after.push(context.base.bytes().to_vec());
+            }
+            Ok(true)
+        }
+    }
+
+    let mut sink = ContextCaptureSink { matched: Vec::new(), after: Vec::new() };
+    let matcher = RegexMatcher::new("MATCH|skip").unwrap();
+    let mut searcher = grep_searcher::SearcherBuilder::new().after_context(1).build();
+
+    searcher
+        .search_slice_with_metadata_filtered(
+            matcher,
+            haystack,
+            Some(&provider),
+            Some(&filter),
+            &mut sink,
+        )
+        .unwrap();
+
+    // Only "MATCH" passes the filter; "skip" does not, so it must never
+    // be reported as a match.
+    assert_eq!(sink.matched, vec![b"MATCH\n".to_vec()]);
+
+    // The after-context budget opened by "MATCH" is 1 line. "skip" spends
+    // that budget even though it fails the filter, so "after" (the next
+    // physical line) is never emitted as after-context.
+    assert!(sink.after.is_empty(), "after-context budget must not stretch past a filtered-out line");
+}
+
+#[test]
+fn test_search_reader_with_metadata() {
+    // Same haystack and regions as test_search_with_metadata_basic, but
+    // driven through a `Read` stream instead of a pre-built `&[u8]`.
+    let haystack = b"Temperature: 25C\nHumidity: 60%\n".to_vec();
+
+    let mut page1_meta = MatchMetadata::new();
+    page1_meta.insert("page", MetaValue::Int(17));
+    let mut page2_meta = MatchMetadata::new();
+    page2_meta.insert("page", MetaValue::Int(18));
+
+    let regions = vec![
+        MetaRegion { start: 0, end: 17, meta: page1_meta },
+        MetaRegion { start: 17, end: 33, meta: page2_meta },
+    ];
+    let provider = VecMetaProvider::new(regions);
+
+    let mut sink = MetadataCaptureSink { matches: Vec::new() };
+    let matcher = RegexMatcher::new("Temp|Humi").unwrap();
+    let mut searcher = Searcher::new();
+
+    searcher
+        .search_reader_with_metadata(
+            matcher,
+            std::io::Cursor::new(haystack),
+            Some(&provider),
+            &mut sink,
+        )
+        .unwrap();
+
+    assert_eq!(sink.matches.len(), 2);
+    assert_eq!(sink.matches[0], (0, Some(17)));
+    assert_eq!(sink.matches[1], (17, Some(18)));
+}
+
+#[test]
+fn test_search_reader_reports_matches_before_binary_byte() {
+    use grep_searcher::BinaryDetection;
+
+    // The `\x00` marks the stream as binary, but "Temperature" precedes
+    // it in the same valid prefix the line buffer hands back -- it must
+    // still be reported, not silently dropped because the search quits
+    // on binary before processing that final chunk.
+    let mut haystack = b"Temperature: 25C\n".to_vec();
+    haystack.extend_from_slice(b"\x00binary junk");
+
+    let mut page1_meta = MatchMetadata::new();
+    page1_meta.insert("page", MetaValue::Int(1));
+    let provider = VecMetaProvider::new(vec![MetaRegion {
+        start: 0,
+        end: haystack.len() as u64,
+        meta: page1_meta,
+    }]);
+
+    let mut sink = MetadataCaptureSink { matches: Vec::new() };
+    let matcher = RegexMatcher::new("Temp").unwrap();
+    let mut searcher = grep_searcher::SearcherBuilder::new()
+        .binary_detection(BinaryDetection::quit(b'\x00'))
+        .build();
+
+    searcher
+        .search_reader_with_metadata(
+            matcher,
+            std::io::Cursor::new(haystack),
+            Some(&provider),
+            &mut sink,
+        )
+        .unwrap();
+
+    assert_eq!(sink.matches, vec![(0, Some(1))]);
+}
+
+#[test]
+fn test_multi_line_reports_full_lines_and_line_numbers() {
+    // A match spanning "line two" through "line three" should be
+    // reported as the full two-line span starting at line 2, not just
+    // the matched substring.
+    let haystack = b"line one\nline two\nline three\nline four\n";
+
+    let mut meta = MatchMetadata::new();
+    meta.insert("chapter", MetaValue::Str("Intro".into()));
+    let provider = VecMetaProvider::new(vec![MetaRegion {
+        start: 0,
+        end: haystack.len() as u64,
+        meta,
+    }]);
+
+    struct FullLineSink {
+        bytes: Vec<u8>,
+        line_number: Option<u64>,
+    }
+
+    impl Sink for FullLineSink {
+        type Error = io::Error;
+        fn matched(&mut self, _: &Searcher, _: &SinkMatch<'_>) -> Result<bool, Self::Error> {
+            panic!("matched() should not be called");
+        }
+    }
+
+    impl SinkWithMeta for FullLineSink {
+        fn matched_with_meta(
+            &mut self,
+            _: &Searcher,
+            mat: &SinkMatchWithMeta<'_, '_>,
+        ) -> Result<bool, Self::Error> {
+            self.bytes = mat.base.bytes().to_vec();
+            self.line_number = mat.base.line_number();
+            Ok(false)
+        }
+    }
+
+    impl SinkContextWithMeta for FullLineSink {}
+
+    let mut sink = FullLineSink { bytes: Vec::new(), line_number: None };
+    let matcher = RegexMatcher::new("(?s)two.*three").unwrap();
+    let mut searcher = grep_searcher::SearcherBuilder::new().multi_line(true).line_number(true).build();
+
+    searcher
+        .search_slice_with_metadata(matcher, haystack, Some(&provider), &mut sink)
+        .unwrap();
+
+    assert_eq!(sink.bytes, b"line two\nline three\n");
+    assert_eq!(sink.line_number, Some(2));
+}